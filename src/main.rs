@@ -1,63 +1,388 @@
 use anyhow::{ensure, Context, Result};
+use clap::{arg, command, Command as ClapCommand};
 use inquire::{required, Text};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use spinoff::{spinners::Dots, Spinner};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 use std::process::{Command, Stdio};
 use ureq::{json, serde_json, serde_json::Value};
 use which::which;
 
 #[derive(Serialize, Deserialize)]
-struct Config {
-    base_url: String,               // Base URL of API endpoints
-    api_key: String,                // Your API key
+struct Profile {
+    base_url: String, // Base URL of API endpoints
+    api_key: String,  // Your API key
     params: Value, // Parameters of the model being used (e.g. https://docs.together.ai/reference/chat-completions)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Config {
+    default_profile: String, // Name of the profile to use when `--profile` isn't passed
+    profiles: HashMap<String, Profile>, // Named provider profiles, e.g. `[profiles.groq]`
     custom_message: Option<String>, // Custom commit message when using JSON mode
+    changelog_params: Value, // Parameters used for the `changelog` subcommand, kept separate so tuning it never touches the commit-message prompt
+    #[serde(default = "default_max_retries")]
+    max_retries: u32, // How many times to ask the model to fix an invalid commit message before giving up
+    #[serde(default)]
+    auto_context: bool, // Derive `branch` and `issue` context from the repo automatically
+    #[serde(default = "default_issue_pattern")]
+    issue_pattern: String, // Regex used to pull a ticket/issue token out of the branch name
+    #[serde(default = "default_max_continuations")]
+    max_continuations: u32, // How many follow-up requests to issue when a response is truncated (`finish_reason == "length"`)
+    #[serde(default)]
+    auto_chunk: bool, // Summarize large staged diffs file-by-file before synthesizing a final commit message
+    #[serde(default = "default_chunk_threshold")]
+    chunk_threshold: usize, // Diff size (in bytes) above which `auto_chunk` kicks in
+    chunk_summary_params: Value, // Parameters used to summarize each file's diff when `auto_chunk` is enabled
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_issue_pattern() -> String {
+    r"[A-Z][A-Z0-9]*-[0-9]+".to_string()
+}
+
+fn default_max_continuations() -> u32 {
+    3
+}
+
+fn default_chunk_threshold() -> usize {
+    6000
+}
+
+impl Config {
+    /// Looks up a named profile, or the `default_profile` when `name` is `None`.
+    ///
+    /// Takes the `profiles`/`default_profile` fields rather than `&self` so callers that
+    /// also need a mutable borrow of another `Config` field (e.g. `changelog_params`) at
+    /// the same time aren't blocked by a whole-struct borrow.
+    fn profile<'a>(
+        profiles: &'a HashMap<String, Profile>,
+        default_profile: &'a str,
+        name: Option<&str>,
+    ) -> Result<&'a Profile> {
+        let name = name.unwrap_or(default_profile);
+
+        profiles.get(name).with_context(|| {
+            format!(
+                "No profile named {name:?} in the config file (available: {:?})",
+                profiles.keys().collect::<Vec<_>>()
+            )
+        })
+    }
+
+    /// Looks up a named profile mutably, or the `default_profile` when `name` is `None`.
+    fn profile_mut(&mut self, name: Option<&str>) -> Result<&mut Profile> {
+        let name = name.unwrap_or(&self.default_profile).to_string();
+
+        self.profiles
+            .get_mut(&name)
+            .with_context(|| format!("No profile named {name:?} in the config file"))
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
+        let default_params = json!({
+            "model": "llama-3-70b-instruct",
+            "max_tokens": 256,
+            "n": 1,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": "\
+                    You will be provided with either the output of the `git diff --staged` command, or a\n\
+                    bullet list of per-file summaries of a large staged diff (when the diff was too big to\n\
+                    include in full).\n\
+                    Your task is to craft a concise and descriptive commit message that accurately reflects the code changes.\n\
+                    \n\
+                    Please adhere to the Conventional Commits specification, formatting the message as follows:\n\
+                    <type>(<scope>): <description>\n\
+                    \n\
+                    - `type`: Choose one of the following based on the nature of the changes:\n\
+                    * feat: A new feature\n\
+                    * fix: A bug fix\n\
+                    * docs: Documentation changes\n\
+                    * style: Changes that do not affect the meaning of the code (formatting, whitespace, etc.)\n\
+                    * refactor: A code change that neither fixes a bug nor adds a feature\n\
+                    * perf: A code change that improves performance\n\
+                    * test: Adding missing tests or correcting existing tests\n\
+                    * build: Changes that affect the build system or external dependencies\n\
+                    * ci: Changes to the CI configuration files and scripts\n\
+                    * chore: Other changes that don't modify src or test files\n\
+                    \n\
+                    - `scope` (optional): A specific area or module of the codebase that the changes affect, enclosed in parentheses (e.g., `feat(parser):`)\n\
+                    - `description`: A concise summary of the changes in a single, lowercase sentence without ending punctuation\n\
+                    \n\
+                    Please provide only the commit message in your response, as it will be used directly in a git commit command.\
+                    "
+                }
+            ]
+        });
+
         Self {
-            base_url: "https://api.perplexity.ai".to_string(),
-            api_key: String::new(),
-            params: json!({
+            default_profile: "default".to_string(),
+            profiles: HashMap::from([(
+                "default".to_string(),
+                Profile {
+                    base_url: "https://api.perplexity.ai".to_string(),
+                    api_key: String::new(),
+                    params: default_params,
+                },
+            )]),
+            custom_message: None,
+            changelog_params: json!({
                 "model": "llama-3-70b-instruct",
-                "max_tokens": 256,
+                "max_tokens": 1024,
                 "n": 1,
                 "messages": [
                     {
                         "role": "system",
                         "content": "\
-                        You will be provided with the output from the `git diff --staged` command.\n\
-                        Your task is to craft a concise and descriptive commit message that accurately reflects the code changes.\n\
-                        \n\
-                        Please adhere to the Conventional Commits specification, formatting the message as follows:\n\
-                        <type>(<scope>): <description>\n\
+                        You will be provided with a Markdown list of commit descriptions, grouped under headings such as\n\
+                        `## Features`, `## Fixes`, `## Performance`, `## Other` and `## Breaking Changes`.\n\
                         \n\
-                        - `type`: Choose one of the following based on the nature of the changes:\n\
-                        * feat: A new feature\n\
-                        * fix: A bug fix\n\
-                        * docs: Documentation changes\n\
-                        * style: Changes that do not affect the meaning of the code (formatting, whitespace, etc.)\n\
-                        * refactor: A code change that neither fixes a bug nor adds a feature\n\
-                        * perf: A code change that improves performance\n\
-                        * test: Adding missing tests or correcting existing tests\n\
-                        * build: Changes that affect the build system or external dependencies\n\
-                        * ci: Changes to the CI configuration files and scripts\n\
-                        * chore: Other changes that don't modify src or test files\n\
+                        Your task is to turn this into a polished, user-facing changelog.\n\
+                        For each heading that is present, write a short Markdown section (reusing the same heading) that\n\
+                        rewrites the bullet points into clear, consistent, user-facing prose, merging duplicates and\n\
+                        dropping anything that is purely internal (e.g. typo fixes in comments).\n\
                         \n\
-                        - `scope` (optional): A specific area or module of the codebase that the changes affect, enclosed in parentheses (e.g., `feat(parser):`)\n\
-                        - `description`: A concise summary of the changes in a single, lowercase sentence without ending punctuation\n\
+                        Please provide only the Markdown changelog in your response, as it will be written directly to a file.\
+                        "
+                    }
+                ]
+            }),
+            max_retries: default_max_retries(),
+            auto_context: false,
+            issue_pattern: default_issue_pattern(),
+            max_continuations: default_max_continuations(),
+            auto_chunk: false,
+            chunk_threshold: default_chunk_threshold(),
+            chunk_summary_params: json!({
+                "model": "llama-3-70b-instruct",
+                "max_tokens": 128,
+                "n": 1,
+                "messages": [
+                    {
+                        "role": "system",
+                        "content": "\
+                        You will be provided with the diff of a single file from a larger `git diff --staged` output.\n\
+                        Summarize the change in one concise sentence, as if it were a bullet point in a commit message.\n\
                         \n\
-                        Please provide only the commit message in your response, as it will be used directly in a git commit command.\
+                        Please provide only the summary in your response.\
                         "
                     }
                 ]
             }),
-            custom_message: None,
         }
     }
 }
 
+/// A parsed Conventional Commit header, e.g. `feat(parser)!: support nested scopes`.
+struct ConventionalHeader<'a> {
+    commit_type: &'a str,
+    description: &'a str,
+}
+
+/// Parses `<type>(<scope>)?!?: <description>` out of a commit subject line.
+/// Returns `None` when the subject has no `: ` separator or its header ends in a `)` with
+/// no opening `(`, so callers
+/// can fall back to bucketing them as "Other". The type itself is not checked against
+/// the known Conventional Commit types.
+fn parse_conventional_header(subject: &str) -> Option<ConventionalHeader<'_>> {
+    let (header, description) = subject.split_once(": ")?;
+
+    let header = header.strip_suffix('!').unwrap_or(header);
+
+    let commit_type = match header.strip_suffix(')') {
+        Some(rest) => {
+            let open_paren = rest.find('(')?;
+            &rest[..open_paren]
+        }
+        None => header,
+    };
+
+    Some(ConventionalHeader {
+        commit_type,
+        description,
+    })
+}
+
+const CONVENTIONAL_COMMIT_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore",
+];
+
+/// Validates a generated commit message against the Conventional Commits spec:
+/// `<type>(<scope>)?!?: <description>` with a lowercase description that doesn't end in punctuation,
+/// plus well-formed `BREAKING CHANGE:` footer lines. Returns a human-readable reason on failure.
+fn validate_commit_message(message: &str) -> std::result::Result<(), String> {
+    let subject = message
+        .lines()
+        .next()
+        .ok_or("The commit message is empty")?;
+
+    let header = parse_conventional_header(subject).ok_or_else(|| {
+        format!("Subject line {subject:?} doesn't match `type(scope): description`")
+    })?;
+
+    if !CONVENTIONAL_COMMIT_TYPES.contains(&header.commit_type) {
+        return Err(format!(
+            "Unknown commit type {:?}; expected one of {CONVENTIONAL_COMMIT_TYPES:?}",
+            header.commit_type
+        ));
+    }
+
+    if header.description.is_empty() {
+        return Err("The description must not be empty".to_string());
+    }
+
+    if header.description != header.description.to_lowercase() {
+        return Err(format!(
+            "The description {:?} must be lowercase",
+            header.description
+        ));
+    }
+
+    if header
+        .description
+        .ends_with(|character: char| character.is_ascii_punctuation())
+    {
+        return Err(format!(
+            "The description {:?} must not end with punctuation",
+            header.description
+        ));
+    }
+
+    for line in message.lines().skip(1) {
+        if let Some(breaking_change) = line.strip_prefix("BREAKING CHANGE:") {
+            if breaking_change.trim().is_empty() {
+                return Err(
+                    "A `BREAKING CHANGE:` footer must be followed by a description".to_string(),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct ChangelogBuckets {
+    features: Vec<String>,
+    fixes: Vec<String>,
+    perf: Vec<String>,
+    other: Vec<String>,
+    breaking: Vec<String>,
+}
+
+/// Buckets `(hash, subject, body)` tuples from `git log` into Conventional Commit
+/// categories, pulling any `BREAKING CHANGE:` footer lines out of the body.
+fn bucket_commits(commits: &[(String, String, String)]) -> ChangelogBuckets {
+    let mut buckets = ChangelogBuckets::default();
+
+    for (_hash, subject, body) in commits {
+        match parse_conventional_header(subject) {
+            Some(header) => {
+                let description = header.description.to_string();
+                match header.commit_type {
+                    "feat" => &mut buckets.features,
+                    "fix" => &mut buckets.fixes,
+                    "perf" => &mut buckets.perf,
+                    _ => &mut buckets.other,
+                }
+                .push(description);
+            }
+            None => buckets.other.push(subject.clone()),
+        }
+
+        buckets.breaking.extend(
+            body.lines()
+                .filter_map(|line| line.strip_prefix("BREAKING CHANGE: "))
+                .map(str::to_string),
+        );
+    }
+
+    buckets
+}
+
+/// Renders the bucketed commits as a structured Markdown list for the LLM to polish.
+fn render_changelog_summary(buckets: &ChangelogBuckets) -> String {
+    let mut summary = String::new();
+
+    for (heading, items) in [
+        ("Features", &buckets.features),
+        ("Fixes", &buckets.fixes),
+        ("Performance", &buckets.perf),
+        ("Other", &buckets.other),
+        ("Breaking Changes", &buckets.breaking),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+
+        summary.push_str(&format!("## {heading}\n"));
+        for item in items {
+            summary.push_str(&format!("- {item}\n"));
+        }
+        summary.push('\n');
+    }
+
+    summary
+}
+
+fn generate_changelog(
+    profile: &Profile,
+    changelog_params: &mut Value,
+    range: &str,
+) -> Result<String> {
+    let log_output = run_git_command(&["log", range, "--reverse", "--pretty=%H%x00%s%x00%b%x00"])?;
+
+    let fields: Vec<&str> = log_output.split('\0').collect();
+
+    let commits: Vec<(String, String, String)> = fields
+        .chunks_exact(3)
+        .map(|chunk| {
+            (
+                chunk[0].trim().to_string(),
+                chunk[1].trim().to_string(),
+                chunk[2].trim().to_string(),
+            )
+        })
+        .collect();
+
+    ensure!(!commits.is_empty(), "No commits found in range {range:?}");
+
+    let summary = render_changelog_summary(&bucket_commits(&commits));
+
+    changelog_params["messages"]
+        .as_array_mut()
+        .context("Missing `messages` parameter in the changelog config")?
+        .push(json!({
+            "role": "user",
+            "content": summary
+        }));
+
+    let response = send_chat_request(&profile.base_url, &profile.api_key, changelog_params)?;
+
+    ensure!(
+        response["choices"][0]["finish_reason"]
+            .as_str()
+            .ne(&Some("length")),
+        "The generated changelog exceeded `max_tokens`"
+    );
+
+    let message = response["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or_default();
+
+    Ok(message.to_string())
+}
+
 fn run_git_command(args: &[&str]) -> Result<String> {
     let command = Command::new("git")
         .args(args)
@@ -73,61 +398,446 @@ fn run_git_command(args: &[&str]) -> Result<String> {
     String::from_utf8(command.stdout).context("The git command returned invalid UTF-8")
 }
 
-fn generate_commit_message(config: &mut Config, git_diffs: &str) -> Result<String> {
-    config.params["messages"]
+const HOOK_NAME: &str = "prepare-commit-msg";
+
+fn hook_path() -> Result<std::path::PathBuf> {
+    let hooks_dir = run_git_command(&["rev-parse", "--git-path", "hooks"])?
+        .trim()
+        .to_string();
+
+    Ok(Path::new(&hooks_dir).join(HOOK_NAME))
+}
+
+/// Installs `acm` as the `prepare-commit-msg` hook, so `git commit` generates the
+/// message automatically instead of requiring a separate `acm` invocation.
+fn install_hook(force: bool) -> Result<()> {
+    let hook_path = hook_path()?;
+
+    ensure!(
+        force || !hook_path.exists(),
+        "A `{HOOK_NAME}` hook already exists at {hook_path:?}; pass `--force` to overwrite it"
+    );
+
+    if let Some(hooks_dir) = hook_path.parent() {
+        fs::create_dir_all(hooks_dir).context("Failed to create the git hooks directory")?;
+    }
+
+    fs::write(&hook_path, "#!/bin/sh\nexec acm --hook \"$1\" \"$2\"\n")
+        .with_context(|| format!("Failed to write the hook script to {hook_path:?}"))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = fs::metadata(&hook_path)?.permissions();
+        permissions.set_mode(0o755);
+        fs::set_permissions(&hook_path, permissions)?;
+    }
+
+    println!("Installed the `{HOOK_NAME}` hook at {hook_path:?}");
+
+    Ok(())
+}
+
+fn uninstall_hook() -> Result<()> {
+    let hook_path = hook_path()?;
+
+    if hook_path.exists() {
+        let contents = fs::read_to_string(&hook_path)
+            .with_context(|| format!("Failed to read the hook script at {hook_path:?}"))?;
+
+        ensure!(
+            contents.contains("exec acm --hook"),
+            "The `{HOOK_NAME}` hook at {hook_path:?} wasn't installed by acm; remove it manually"
+        );
+
+        fs::remove_file(&hook_path)
+            .with_context(|| format!("Failed to remove the hook script at {hook_path:?}"))?;
+    }
+
+    println!("Removed the `{HOOK_NAME}` hook at {hook_path:?}");
+
+    Ok(())
+}
+
+/// Derives `branch` and, if `issue_pattern` matches it, `issue` context from the current repo.
+fn collect_auto_context(issue_pattern: &str) -> Result<Vec<(String, String)>> {
+    let mut context = Vec::new();
+
+    let branch = run_git_command(&["rev-parse", "--abbrev-ref", "HEAD"])?
+        .trim()
+        .to_string();
+
+    if branch.is_empty() || branch == "HEAD" {
+        return Ok(context);
+    }
+
+    context.push(("branch".to_string(), branch.clone()));
+
+    let issue_regex =
+        Regex::new(issue_pattern).context("Invalid `issue_pattern` regex in the config file")?;
+
+    if let Some(issue) = issue_regex.find(&branch) {
+        context.push(("issue".to_string(), issue.as_str().to_string()));
+    }
+
+    Ok(context)
+}
+
+/// Renders key/value context as a labeled block to prepend to the user message.
+fn render_context_block(context: &[(String, String)]) -> String {
+    if context.is_empty() {
+        return String::new();
+    }
+
+    let mut block = String::from("Context:\n");
+    for (key, value) in context {
+        block.push_str(&format!("- {key}: {value}\n"));
+    }
+    block.push('\n');
+
+    block
+}
+
+fn send_chat_request(base_url: &str, api_key: &str, params: &Value) -> Result<Value> {
+    ureq::post(&format!("{base_url}/chat/completions"))
+        .set("Authorization", &format!("Bearer {api_key}"))
+        .send_json(params)?
+        .into_json::<Value>()
+        .context("Failed to parse the API response")
+}
+
+/// Sends `profile.params` and, while the response is truncated (`finish_reason == "length"`),
+/// asks the model to continue, concatenating the pieces until it finishes or the
+/// continuation budget runs out.
+fn complete_with_continuation(profile: &mut Profile, max_continuations: u32) -> Result<String> {
+    let mut content = String::new();
+
+    for attempt in 0..=max_continuations {
+        let response = send_chat_request(&profile.base_url, &profile.api_key, &profile.params)?;
+
+        let finish_reason = response["choices"][0]["finish_reason"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let piece = response["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default();
+
+        content.push_str(piece);
+
+        if finish_reason != "length" || attempt == max_continuations {
+            // Best-effort: hand back what we have even if it's still truncated.
+            return Ok(content);
+        }
+
+        let messages = profile.params["messages"]
+            .as_array_mut()
+            .context("Missing `messages` parameter in the config file")?;
+        messages.push(json!({"role": "assistant", "content": piece}));
+        messages.push(json!({"role": "user", "content": "Continue exactly where you left off."}));
+    }
+
+    Ok(content)
+}
+
+fn generate_commit_message(
+    profile: &mut Profile,
+    custom_message: &Option<String>,
+    max_retries: u32,
+    max_continuations: u32,
+    context: &[(String, String)],
+    git_diffs: &str,
+) -> Result<String> {
+    profile.params["messages"]
         .as_array_mut()
         .context("Missing `messages` parameter in the config file")?
         .push(json!({
             "role": "user",
-            "content": git_diffs
+            "content": format!("{}{git_diffs}", render_context_block(context))
         }));
 
-    let response = ureq::post(&format!("{}/chat/completions", &config.base_url))
-        .set("Authorization", &format!("Bearer {}", &config.api_key))
-        .send_json(&config.params)?
-        .into_json::<Value>()?;
+    let json_mode = profile.params["response_format"]["type"]
+        .as_str()
+        .eq(&Some("json_object"));
+
+    let mut message = String::new();
+
+    for attempt in 0..=max_retries {
+        message = complete_with_continuation(profile, max_continuations)?;
+
+        if json_mode {
+            // JSON mode produces an arbitrary structured payload rather than a
+            // Conventional Commit subject, so the validator doesn't apply.
+            if let Some(custom_message) = custom_message {
+                let json_message = serde_json::from_str::<Value>(&message)?;
+
+                return Ok(custom_message
+                    .split("||")
+                    .map(|chunk| {
+                        json_message
+                            .pointer(chunk)
+                            .and_then(|value| value.as_str())
+                            .unwrap_or(chunk)
+                    })
+                    .collect::<String>());
+            }
+
+            return Ok(message);
+        }
+
+        match validate_commit_message(&message) {
+            Ok(()) => return Ok(message),
+            Err(reason) if attempt < max_retries => {
+                profile.params["messages"]
+                    .as_array_mut()
+                    .context("Missing `messages` parameter in the config file")?
+                    .push(json!({
+                        "role": "user",
+                        "content": format!(
+                            "That message was invalid: {reason}\n\nHere is what you sent:\n\n{message}\n\nPlease send a corrected commit message that follows the Conventional Commits format described above."
+                        )
+                    }));
+            }
+            Err(_) => break,
+        }
+    }
+
+    // Best-effort: hand back the last response even if it never passed validation.
+    Ok(message)
+}
+
+/// Splits a `git diff --staged` output into one chunk per file, on `diff --git` boundaries.
+fn split_diff_by_file(diff: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git") && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+fn summarize_diff_chunk(
+    profile: &Profile,
+    chunk_summary_params: &Value,
+    diff_chunk: &str,
+) -> Result<String> {
+    let mut params = chunk_summary_params.clone();
+    params["messages"]
+        .as_array_mut()
+        .context("Missing `messages` parameter in the chunk summary config")?
+        .push(json!({
+            "role": "user",
+            "content": diff_chunk
+        }));
+
+    let response = send_chat_request(&profile.base_url, &profile.api_key, &params)?;
 
     ensure!(
         response["choices"][0]["finish_reason"]
             .as_str()
             .ne(&Some("length")),
-        "The generated message exceeded `max_tokens`"
+        "A per-file diff summary exceeded `max_tokens`"
     );
 
-    let message = response["choices"][0]["message"]["content"]
+    Ok(response["choices"][0]["message"]["content"]
         .as_str()
-        .unwrap_or_default();
+        .unwrap_or_default()
+        .to_string())
+}
 
-    if config.params["response_format"]["type"]
-        .as_str()
-        .eq(&Some("json_object"))
-    {
-        if let Some(custom_message) = &config.custom_message {
-            let json_message = serde_json::from_str::<Value>(message)?;
-
-            return Ok(custom_message
-                .split("||")
-                .map(|chunk| {
-                    json_message
-                        .pointer(chunk)
-                        .and_then(|value| value.as_str())
-                        .unwrap_or(chunk)
-                })
-                .collect::<String>());
-        }
+/// When `auto_chunk` is enabled and `git_diffs` is large, replaces it with per-file
+/// summaries so `generate_commit_message` synthesizes from those instead of the raw diff.
+fn maybe_chunk_diffs(
+    config: &Config,
+    profile_name: Option<&str>,
+    git_diffs: String,
+) -> Result<String> {
+    if !config.auto_chunk || git_diffs.len() <= config.chunk_threshold {
+        return Ok(git_diffs);
     }
 
-    Ok(message.to_string())
+    let profile = Config::profile(&config.profiles, &config.default_profile, profile_name)?;
+
+    let summaries = split_diff_by_file(&git_diffs)
+        .iter()
+        .map(|chunk| summarize_diff_chunk(profile, &config.chunk_summary_params, chunk))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut combined =
+        String::from("The following are per-file summaries of a large staged diff:\n\n");
+    for summary in summaries {
+        combined.push_str(&format!("- {summary}\n"));
+    }
+
+    Ok(combined)
 }
 
 fn main() -> Result<()> {
-    clap::command!().get_matches(); // Add the `--version` flag to the CLI
+    let matches = command!()
+        .arg(
+            arg!(--profile <NAME> "Named provider profile to use (defaults to `default_profile` in the config file)")
+                .required(false)
+                .global(true),
+        )
+        .subcommand(
+            ClapCommand::new("changelog")
+                .about("Summarize the commits between two refs into a Markdown changelog")
+                .arg(arg!(--from <TAG> "Ref to start from (exclusive); defaults to the beginning of history").required(false))
+                .arg(arg!(--to <REF> "Ref to end at (inclusive)").default_value("HEAD")),
+        )
+        .subcommand(
+            ClapCommand::new("hook")
+                .about("Manage the git `prepare-commit-msg` hook")
+                .subcommand_required(true)
+                .subcommand(
+                    ClapCommand::new("install")
+                        .about("Install acm as the prepare-commit-msg hook")
+                        .arg(arg!(--force "Overwrite an existing prepare-commit-msg hook").required(false)),
+                )
+                .subcommand(ClapCommand::new("uninstall").about("Remove the acm prepare-commit-msg hook")),
+        )
+        .arg(
+            arg!(--hook <FILE> "Internal: run as a prepare-commit-msg hook, writing the message to FILE")
+                .required(false)
+                .hide(true),
+        )
+        .arg(
+            arg!([source] "Internal: the source argument git passes to prepare-commit-msg")
+                .required(false)
+                .hide(true),
+        )
+        .arg(
+            arg!(-c --context <KEY_VALUE> "Extra `key=value` context to give the model (e.g. `--context ticket=PROJ-123`), repeatable")
+                .required(false)
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .get_matches();
 
     which("git").context("Unable to find git executable in PATH")?;
 
     run_git_command(&["rev-parse", "--is-inside-work-tree"])
         .context("The current directory is not a git repository")?;
 
+    if let Some(hook_matches) = matches.subcommand_matches("hook") {
+        return match hook_matches.subcommand() {
+            Some(("install", install_matches)) => install_hook(install_matches.get_flag("force")),
+            Some(("uninstall", _)) => uninstall_hook(),
+            _ => unreachable!("clap enforces a hook subcommand"),
+        };
+    }
+
+    let config_file = dirs::home_dir()
+        .context("Failed to get the home directory")?
+        .join(".config/acm/config.toml");
+
+    let mut config = confy::load_path::<Config>(&config_file)?;
+    let profile_name = matches.get_one::<String>("profile").map(String::as_str);
+
+    ensure!(
+        !Config::profile(&config.profiles, &config.default_profile, profile_name)?
+            .api_key
+            .is_empty(),
+        "Please provide your API key in the config file created at {:?}",
+        config_file
+    );
+
+    let mut context = if config.auto_context {
+        collect_auto_context(&config.issue_pattern)?
+    } else {
+        Vec::new()
+    };
+
+    if let Some(values) = matches.get_many::<String>("context") {
+        for value in values {
+            let (key, value) = value
+                .split_once('=')
+                .with_context(|| format!("--context {value:?} must be in `key=value` form"))?;
+            context.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    if let Some(commit_msg_file) = matches.get_one::<String>("hook") {
+        // Git passes a source of `message`/`template`/`merge`/`squash`/`commit` when the
+        // message is already supplied some other way (`-m`, `-c`, merges, `--amend`, ...);
+        // leave those untouched instead of overwriting the user's or git's own content.
+        let source = matches.get_one::<String>("source").map(String::as_str);
+        if matches!(
+            source,
+            Some("message" | "template" | "merge" | "squash" | "commit")
+        ) {
+            return Ok(());
+        }
+
+        let git_diffs = run_git_command(&[
+            "--no-pager",
+            "diff",
+            "--staged",
+            "--minimal",
+            "--no-color",
+            "--function-context",
+            "--no-ext-diff",
+            "--",
+            ":(exclude)*.lock*",
+            ":(exclude)*-lock.*",
+        ])?
+        .trim()
+        .to_string();
+
+        if git_diffs.is_empty() {
+            return Ok(());
+        }
+
+        let git_diffs = maybe_chunk_diffs(&config, profile_name, git_diffs)?;
+        let custom_message = config.custom_message.clone();
+        let max_retries = config.max_retries;
+        let max_continuations = config.max_continuations;
+        let profile = config.profile_mut(profile_name)?;
+        let commit_message = generate_commit_message(
+            profile,
+            &custom_message,
+            max_retries,
+            max_continuations,
+            &context,
+            &git_diffs,
+        )?;
+
+        fs::write(commit_msg_file, commit_message).with_context(|| {
+            format!("Failed to write the commit message to {commit_msg_file:?}")
+        })?;
+
+        return Ok(());
+    }
+
+    if let Some(changelog_matches) = matches.subcommand_matches("changelog") {
+        let to = changelog_matches.get_one::<String>("to").unwrap();
+        let range = match changelog_matches.get_one::<String>("from") {
+            Some(from) => format!("{from}..{to}"),
+            None => to.to_string(),
+        };
+
+        let profile = Config::profile(&config.profiles, &config.default_profile, profile_name)?;
+
+        let mut spinner = Spinner::new(Dots, "Generating a changelog", None);
+        let changelog = generate_changelog(profile, &mut config.changelog_params, &range);
+        spinner.stop_with_message("");
+
+        println!("{}", changelog?);
+
+        return Ok(());
+    }
+
     let git_diffs = run_git_command(&[
         "--no-pager",
         "diff",
@@ -145,21 +855,22 @@ fn main() -> Result<()> {
 
     ensure!(!git_diffs.is_empty(), "No changes staged for commit");
 
-    let config_file = dirs::home_dir()
-        .context("Failed to get the home directory")?
-        .join(".config/acm/config.toml");
-
-    let mut config = confy::load_path::<Config>(&config_file)?;
-
-    ensure!(
-        !config.api_key.is_empty(),
-        "Please provide your API key in the config file created at {:?}",
-        config_file
-    );
+    let git_diffs = maybe_chunk_diffs(&config, profile_name, git_diffs)?;
+    let custom_message = config.custom_message.clone();
+    let max_retries = config.max_retries;
+    let max_continuations = config.max_continuations;
+    let profile = config.profile_mut(profile_name)?;
 
     let mut spinner = Spinner::new(Dots, "Generating a commit message", None);
 
-    let commit_message = generate_commit_message(&mut config, &git_diffs);
+    let commit_message = generate_commit_message(
+        profile,
+        &custom_message,
+        max_retries,
+        max_continuations,
+        &context,
+        &git_diffs,
+    );
 
     spinner.stop_with_message("");
 
@@ -176,3 +887,68 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_conventional_header_plain() {
+        let header = parse_conventional_header("fix: correct off-by-one error").unwrap();
+        assert_eq!(header.commit_type, "fix");
+        assert_eq!(header.description, "correct off-by-one error");
+    }
+
+    #[test]
+    fn parse_conventional_header_with_scope_and_breaking_marker() {
+        let header = parse_conventional_header("feat(parser)!: support nested scopes").unwrap();
+        assert_eq!(header.commit_type, "feat");
+        assert_eq!(header.description, "support nested scopes");
+    }
+
+    #[test]
+    fn parse_conventional_header_rejects_unparseable_subject() {
+        assert!(parse_conventional_header("oops forgot the format").is_none());
+    }
+
+    #[test]
+    fn validate_commit_message_accepts_scoped_breaking_change() {
+        assert!(validate_commit_message("feat(parser)!: support nested scopes").is_ok());
+    }
+
+    #[test]
+    fn validate_commit_message_rejects_uppercase_description() {
+        assert!(validate_commit_message("fix: Correct off-by-one error").is_err());
+    }
+
+    #[test]
+    fn validate_commit_message_rejects_trailing_punctuation() {
+        assert!(validate_commit_message("fix: correct off-by-one error.").is_err());
+    }
+
+    #[test]
+    fn validate_commit_message_rejects_empty_breaking_change_footer() {
+        assert!(
+            validate_commit_message("fix: correct off-by-one error\n\nBREAKING CHANGE:").is_err()
+        );
+    }
+
+    #[test]
+    fn bucket_commits_sorts_scoped_breaking_change_into_features() {
+        let commits = vec![(
+            "abc123".to_string(),
+            "feat(parser)!: support nested scopes".to_string(),
+            "BREAKING CHANGE: nested scopes are no longer flattened".to_string(),
+        )];
+
+        let buckets = bucket_commits(&commits);
+
+        assert_eq!(buckets.features, vec!["support nested scopes".to_string()]);
+        assert!(buckets.fixes.is_empty());
+        assert!(buckets.other.is_empty());
+        assert_eq!(
+            buckets.breaking,
+            vec!["nested scopes are no longer flattened".to_string()]
+        );
+    }
+}